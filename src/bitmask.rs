@@ -1,3 +1,4 @@
+#[cfg(target_feature = "sse3")]
 use std::arch::x86_64::*;
 use std::ops::Deref;
 
@@ -25,6 +26,50 @@ impl BitMask {
             BitMask::new(_mm_movemask_epi8(_mm_cmpeq_epi8(vec, pred)) as u16)
         }
     }
+
+    #[inline(always)]
+    #[cfg(not(target_feature = "sse3"))]
+    /// Portable SWAR (SIMD-within-a-register) fallback for platforms without SSE3 --
+    /// ARM, RISC-V, wasm, or x86 built without `target-feature=+sse3`. Operates on the
+    /// same 16-byte control window as two `u64` lanes and produces the same bitmask the
+    /// SSE path would.
+    ///
+    /// This is the classic zero-byte-detection SWAR trick applied to `byte ^ predicate`,
+    /// and like that trick it can produce false-positive bits: e.g. a lane holding
+    /// `0x01` against a predicate of `0x00` yields the same high-bit pattern as an
+    /// actual zero byte once combined with neighbouring lanes. A false-positive bit
+    /// always marks a byte equal to `predicate ^ 0x01`. `probe_hashed` and
+    /// `get_or_recompute` re-check the exact control byte (`val == ctrl`) at each
+    /// candidate offset before trusting a match, so a false positive there just costs an
+    /// extra comparison. `occupied_mask` uses the mask alone; for its `Empty`/`Deleted`
+    /// predicates a false positive would need a `0xFF`/`0x81` byte, which no control
+    /// byte ever holds.
+    ///
+    /// Panics if `vec.len() < 16`
+    pub fn matches(vec: &[u8], predicate: u8) -> Self {
+        let lo = u64::from_le_bytes(vec[0..8].try_into().unwrap());
+        let hi = u64::from_le_bytes(vec[8..16].try_into().unwrap());
+        let repeated = predicate as u64 * 0x0101_0101_0101_0101;
+        let lo_mask = Self::zero_byte_mask(lo ^ repeated);
+        let hi_mask = Self::zero_byte_mask(hi ^ repeated);
+        BitMask::new(lo_mask as u16 | ((hi_mask as u16) << 8))
+    }
+
+    /// Given `cmp`, a word whose zero bytes mark the lanes equal to `predicate`, returns
+    /// a compact 8-bit mask with bit `i` set when byte `i` of `cmp` is zero, and also for
+    /// a `0x01` byte that a borrow from a zero byte below it reaches (see `matches`).
+    #[inline(always)]
+    #[cfg(not(target_feature = "sse3"))]
+    fn zero_byte_mask(cmp: u64) -> u8 {
+        let high_bits = cmp.wrapping_sub(0x0101_0101_0101_0101) & !cmp & 0x8080_8080_8080_8080;
+        let mut mask = 0u8;
+        for byte_index in 0..8 {
+            if high_bits & (0x80 << (byte_index * 8)) != 0 {
+                mask |= 1 << byte_index;
+            }
+        }
+        mask
+    }
 }
 
 #[cfg(not(feature = "nightly"))]
@@ -58,6 +103,18 @@ impl Iterator for BitMask {
     }
 }
 
+impl std::ops::BitOr for BitMask {
+    type Output = BitMask;
+
+    /// Combines two match masks over the same 16-byte control group -- `probe` and
+    /// friends use this to walk "matches this hash OR is empty" in a single pass instead
+    /// of two.
+    #[inline(always)]
+    fn bitor(self, rhs: Self) -> Self::Output {
+        BitMask::new(self.mask | rhs.mask)
+    }
+}
+
 impl Into<bool> for BitMask {
     #[inline(always)]
     fn into(self) -> bool {
@@ -3,6 +3,7 @@ use std::hash::{BuildHasher, Hash};
 use std::marker::PhantomData;
 use std::mem;
 use std::sync::atomic::AtomicUsize;
+use std::time::{Duration, Instant};
 
 mod bitmask;
 
@@ -11,19 +12,122 @@ pub use bitmask::BitMask;
 use ahash::CallHasher;
 use lazy_static::lazy_static;
 
-#[derive(Copy, Clone, Debug)]
-struct Node<V> {
+/// Recent operations recorded against a `StampedeMap`, for the `diagnostics` feature's
+/// journal (see [`Journal`]).
+///
+/// Only the hash is kept, not the key, so this stays `Copy` and cheap to push even for
+/// expensive `K`s -- a journal dump is meant to localize *when* an invariant broke, not
+/// to replay the exact operations.
+#[cfg(feature = "diagnostics")]
+#[derive(Debug, Clone, Copy)]
+enum JournalEntry {
+    Insert(u64),
+    Get(u64),
+    Remove(u64),
+    DidClear(usize),
+}
+
+#[cfg(feature = "diagnostics")]
+const JOURNAL_CAPACITY: usize = 32;
+
+/// Bounded ring buffer of the last [`JOURNAL_CAPACITY`] operations performed against a
+/// `StampedeMap`, kept only when the `diagnostics` feature is enabled.
+///
+/// Dumped into the panic message when a canary or invariant check trips, so a
+/// heap-overwrite bug is reported at the operation that noticed it instead of silently
+/// corrupting state that only fails much later (or not at all, as UB).
+#[cfg(feature = "diagnostics")]
+#[derive(Debug, Clone)]
+struct Journal {
+    entries: Vec<JournalEntry>,
+    next: usize,
+}
+
+#[cfg(feature = "diagnostics")]
+impl Journal {
+    fn new() -> Self {
+        Self {
+            entries: Vec::with_capacity(JOURNAL_CAPACITY),
+            next: 0,
+        }
+    }
+
+    fn record(&mut self, entry: JournalEntry) {
+        if self.entries.len() < JOURNAL_CAPACITY {
+            self.entries.push(entry);
+        } else {
+            self.entries[self.next] = entry;
+        }
+        self.next = (self.next + 1) % JOURNAL_CAPACITY;
+    }
+
+    /// Entries oldest-to-newest.
+    fn ordered(&self) -> Vec<JournalEntry> {
+        if self.entries.len() < JOURNAL_CAPACITY {
+            return self.entries.clone();
+        }
+        let mut out = Vec::with_capacity(JOURNAL_CAPACITY);
+        out.extend_from_slice(&self.entries[self.next..]);
+        out.extend_from_slice(&self.entries[..self.next]);
+        out
+    }
+}
+
+/// Sentinel word for the `diagnostics` feature's canary guards. Chosen to look nothing
+/// like a plausible `len`/`capacity`/pointer value, so a stray write landing on a canary
+/// is obvious in a dump rather than passing for real bookkeeping data.
+#[cfg(feature = "diagnostics")]
+const CANARY: u64 = 0xC0FF_EEDE_AD00_BEEF;
+
+/// Trailing bytes appended to the *actual* `ctrl` allocation, past the bookkeeping mirror
+/// group, holding [`CANARY`]. A `Vec`'s allocation starts exactly at its first element --
+/// there's no addressable slack before index 0 to guard -- so only a trailing guard can
+/// genuinely sit inside the same heap buffer `get_unchecked`/`get_unchecked_mut` index
+/// past the end of.
+#[cfg(feature = "diagnostics")]
+const CANARY_GUARD_LEN: usize = 8;
+
+/// Default `beta` for [`StampedeMap::get_or_recompute`]'s XFetch early-expiration curve.
+///
+/// `1.0` matches the value used in the paper this algorithm is named for
+/// ("Optimal Probabilistic Cache Stampede Prevention", Vattani et al.); higher values
+/// spread recomputes out earlier and more aggressively.
+const DEFAULT_BETA: f64 = 1.0;
+
+#[derive(Clone, Debug)]
+struct Node<K, V> {
     hash: u64,
+    key: K,
     value: V,
+    // when this entry was computed to expire, and how long the last `compute` took to
+    // produce it -- both are only meaningful for entries written through
+    // `get_or_recompute`; plain `set` entries carry `delta == Duration::ZERO`, which
+    // `get_or_recompute` treats as "always stale".
+    expiry: Instant,
+    delta: Duration,
+}
+
+impl<K, V> Node<K, V> {
+    #[inline(always)]
+    fn new(hash: u64, key: K, value: V) -> Self {
+        Self {
+            hash,
+            key,
+            value,
+            expiry: Instant::now(),
+            delta: Duration::ZERO,
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
-enum Slot<V>
+enum Slot<K, V>
 where
+    K: Clone,
     V: Clone,
 {
     Empty,
-    Occupied(Node<V>),
+    Occupied(Node<K, V>),
 }
 
 #[inline(always)]
@@ -35,12 +139,50 @@ const fn ctrl_hash(hash: u64) -> u8 {
 const Deleted: u8 = 0b1000_0000;
 const Empty: u8 = 0b1111_1110;
 
+/// Bitmask of occupied slots within a 16-byte control group: every bit whose control
+/// byte is neither `Empty` nor `Deleted`.
+#[inline(always)]
+fn occupied_mask(buffer: &[u8]) -> u16 {
+    let empty = *BitMask::matches(buffer, Empty);
+    let deleted = *BitMask::matches(buffer, Deleted);
+    !(empty | deleted)
+}
+
+/// Returned by the `try_*` growth APIs when the allocator can't satisfy a requested
+/// capacity. The map is left exactly as it was before the call.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TryReserveError {
+    requested_capacity: usize,
+}
+
+impl TryReserveError {
+    fn new(requested_capacity: usize) -> Self {
+        Self { requested_capacity }
+    }
+}
+
+impl std::fmt::Display for TryReserveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "StampedeMap: failed to allocate capacity for {} slots",
+            self.requested_capacity
+        )
+    }
+}
+
+impl std::error::Error for TryReserveError {}
+
 #[derive(Debug)]
 pub struct StampedeMap<K, V, S = ahash::RandomState>
 where
+    K: Clone,
     V: Clone,
 {
-    data: Vec<Slot<V>>,
+    // `data`'s and `ctrl`'s real allocations each carry extra trailing bytes/slots past
+    // `capacity` under `diagnostics`, holding guard values `check_canaries` verifies; see
+    // `data_alloc_len`/`ctrl_alloc_len`.
+    data: Vec<Slot<K, V>>,
     // extra_data:
     counter: AtomicUsize,
     len: usize,
@@ -48,7 +190,15 @@ where
     ctrl: Vec<u8>,
     // keep track of tombstone count because they contribute to load factor
     deleted: usize,
+    // XFetch early-expiration coefficient used by `get_or_recompute`; see `with_beta`.
+    beta: f64,
     _phantom: PhantomData<(K, S)>,
+    // `RefCell`, not a bare field: `record` is called from `probe`, which only takes
+    // `&self` (shared across `get`/`get_key_value`/`contains_key`/`delete`/`entry`), so
+    // appending to the journal needs real interior mutability rather than casting away
+    // the shared borrow.
+    #[cfg(feature = "diagnostics")]
+    journal: std::cell::RefCell<Journal>,
 }
 
 #[inline(always)]
@@ -56,40 +206,176 @@ const fn bucket_size() -> usize {
     16
 }
 
+/// Real allocation length for `ctrl` given a logical `capacity`: the `capacity` live
+/// control bytes, one extra group (`bucket_size()`) mirroring the first group so a probe
+/// that wraps past the end can still read 16 bytes unchecked, and -- under `diagnostics`
+/// -- [`CANARY_GUARD_LEN`] trailing bytes belonging to no logical slot at all, checked by
+/// `check_canaries`.
+#[cfg(feature = "diagnostics")]
+#[inline(always)]
+const fn ctrl_alloc_len(capacity: usize) -> usize {
+    capacity + bucket_size() + CANARY_GUARD_LEN
+}
+
+#[cfg(not(feature = "diagnostics"))]
+#[inline(always)]
+const fn ctrl_alloc_len(capacity: usize) -> usize {
+    capacity + bucket_size()
+}
+
+/// Real allocation length for `data` given a logical `capacity`: under `diagnostics`, one
+/// extra trailing `Slot` beyond every index `modulo()` can ever produce, which
+/// `check_canaries` expects to stay `Slot::Empty` forever -- any write there can only come
+/// from a bug that walked past the end of the real table.
+#[cfg(feature = "diagnostics")]
+#[inline(always)]
+const fn data_alloc_len(capacity: usize) -> usize {
+    capacity + 1
+}
+
+#[cfg(not(feature = "diagnostics"))]
+#[inline(always)]
+const fn data_alloc_len(capacity: usize) -> usize {
+    capacity
+}
+
 impl<K, V, S> StampedeMap<K, V, S>
 where
-    K: Hash + Sized + CallHasher,
+    K: Hash + Sized + CallHasher + Eq + Clone,
     V: Clone + std::fmt::Debug,
     S: BuildHasher + Default,
 {
     pub fn new() -> Self {
-        Self {
-            data: vec![Slot::Empty; bucket_size()],
+        let capacity = bucket_size();
+        let mut map = Self {
+            data: vec![Slot::Empty; data_alloc_len(capacity)],
             counter: AtomicUsize::new(0),
-            ctrl: vec![Empty; bucket_size() * 2], // extra group for bookkeeping
-            capacity: bucket_size(),
+            ctrl: vec![Empty; ctrl_alloc_len(capacity)], // extra group for bookkeeping
+            capacity,
             deleted: 0,
+            beta: DEFAULT_BETA,
             _phantom: PhantomData,
             len: 0,
-        }
+            #[cfg(feature = "diagnostics")]
+            journal: std::cell::RefCell::new(Journal::new()),
+        };
+        #[cfg(feature = "diagnostics")]
+        map.write_ctrl_canary();
+        map
+    }
+
+    /// Overrides the XFetch `beta` coefficient used by [`Self::get_or_recompute`].
+    ///
+    /// `beta` scales how far ahead of the true expiry callers start racing to recompute;
+    /// the default of `1.0` is a reasonable starting point, values above that spread
+    /// recomputes earlier (fewer callers hit true expiry at once, at the cost of more
+    /// total recomputes).
+    pub fn with_beta(mut self, beta: f64) -> Self {
+        self.beta = beta;
+        self
     }
 
     pub fn with_capacity(cap: usize) -> Self {
         let mut map = Self::new();
         let cap = cap.next_power_of_two();
         map.capacity = cap;
-        map.data.resize(map.capacity, Slot::Empty);
-        map.ctrl.resize(map.capacity + 16, Empty);
+        map.data.resize(data_alloc_len(map.capacity), Slot::Empty);
+        map.ctrl = vec![Empty; ctrl_alloc_len(map.capacity)];
+        #[cfg(feature = "diagnostics")]
+        map.write_ctrl_canary();
         map
     }
 
     pub fn clear(&mut self) {
-        let mut vec = vec![Slot::Empty; self.capacity];
-        let mut ctrl = vec![Empty; self.capacity + 16];
+        #[cfg(feature = "diagnostics")]
+        self.check_canaries();
+        #[cfg(feature = "diagnostics")]
+        self.record(JournalEntry::DidClear(self.len));
+        let mut vec = vec![Slot::Empty; data_alloc_len(self.capacity)];
+        let mut ctrl = vec![Empty; ctrl_alloc_len(self.capacity)];
         mem::swap(&mut self.data, &mut vec);
         mem::swap(&mut self.ctrl, &mut ctrl);
         self.deleted = 0;
         self.len = 0;
+        #[cfg(feature = "diagnostics")]
+        self.write_ctrl_canary();
+    }
+
+    /// Borrowing iterator over all occupied entries, in slot order.
+    ///
+    /// Slices off the trailing canary guard (see `data_alloc_len`/`ctrl_alloc_len`) so the
+    /// iterator's own bounds stay exactly `[0, capacity)` whether or not `diagnostics` is
+    /// enabled.
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter::new(&self.ctrl[..self.capacity + 16], &self.data[..self.capacity])
+    }
+
+    /// Borrowing iterator over all occupied entries, with mutable values.
+    pub fn iter_mut(&mut self) -> IterMut<'_, K, V> {
+        IterMut::new(&self.ctrl[..self.capacity + 16], &mut self.data[..self.capacity])
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        self.iter().map(|(k, _)| k)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &V> {
+        self.iter().map(|(_, v)| v)
+    }
+
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut V> {
+        self.iter_mut().map(|(_, v)| v)
+    }
+
+    /// Removes every entry and returns an iterator yielding the owned `(K, V)` pairs,
+    /// same as `clear` but handing the removed entries back instead of dropping them.
+    pub fn drain(&mut self) -> Drain<K, V> {
+        #[cfg(feature = "diagnostics")]
+        self.check_canaries();
+        let mut data = vec![Slot::Empty; data_alloc_len(self.capacity)];
+        let mut ctrl = vec![Empty; ctrl_alloc_len(self.capacity)];
+        mem::swap(&mut self.data, &mut data);
+        mem::swap(&mut self.ctrl, &mut ctrl);
+        self.deleted = 0;
+        self.len = 0;
+        #[cfg(feature = "diagnostics")]
+        self.write_ctrl_canary();
+        Drain {
+            inner: data.into_iter(),
+        }
+    }
+
+    /// Keeps only the entries for which `f` returns `true`, dropping the rest the same
+    /// way `delete` would (control byte + mirror set to `Deleted`, `deleted`/`len` kept
+    /// in sync).
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&K, &mut V) -> bool,
+    {
+        #[cfg(feature = "diagnostics")]
+        self.check_canaries();
+        let mut group = 0;
+        while group * 16 < self.capacity {
+            let start = group * 16;
+            let mask = occupied_mask(&self.ctrl[start..start + 16]);
+            for item in BitMask::new(mask) {
+                let idx = start + item as usize;
+                let keep = match &mut self.data[idx] {
+                    Slot::Occupied(node) => f(&node.key, &mut node.value),
+                    Slot::Empty => unreachable!(),
+                };
+                if !keep {
+                    if (0..16).contains(&idx) {
+                        self.ctrl[self.capacity + idx] = Deleted;
+                    }
+                    self.ctrl[idx] = Deleted;
+                    self.data[idx] = Slot::Empty;
+                    self.deleted += 1;
+                    self.len -= 1;
+                }
+            }
+            group += 1;
+        }
     }
 
     pub fn len(&self) -> usize {
@@ -104,9 +390,24 @@ where
         self.capacity
     }
 
+    /// Probes for `key`, returning the index of its occupied slot if present.
+    ///
+    /// Shared by `get`, `get_key_value`, `contains_key` and `delete`: a control-byte +
+    /// hash match is only a candidate, since two distinct keys can collide in 64 bits
+    /// (this was `get`'s one bug before keys were stored at all -- it trusted the hash
+    /// match alone), so the key itself is always compared before accepting a slot.
+    #[inline(always)]
+    fn probe(&self, key: &K) -> Option<usize> {
+        self.probe_hashed(self.hash(key), key)
+    }
+
+    /// Core of `probe`, taking an already-computed hash so callers that need the hash
+    /// for something else too (`entry`, building a new `Node`) don't pay to hash `key`
+    /// twice.
     #[inline(never)]
-    pub fn get(&self, key: K) -> Option<&V> {
-        let hash = self.hash(&key);
+    fn probe_hashed(&self, hash: u64, key: &K) -> Option<usize> {
+        #[cfg(feature = "diagnostics")]
+        self.record(JournalEntry::Get(hash));
         let ctrl = ctrl_hash(hash);
         let mut slot = self.modulo(hash);
         loop {
@@ -123,8 +424,15 @@ where
                     // SAFETY: the `modulo` method ensures we cannot perform an out of bounds read
                     val if val == ctrl => match unsafe { self.data.get_unchecked(offset) } {
                         // the ctrl byte should be set to Empty
+                        #[cfg(not(feature = "diagnostics"))]
                         Slot::Empty => unreachable!(),
-                        Slot::Occupied(ref node) if node.hash == hash => return Some(&node.value),
+                        #[cfg(feature = "diagnostics")]
+                        Slot::Empty => {
+                            self.bug("control byte claims a slot is occupied but data[idx] is Slot::Empty")
+                        }
+                        Slot::Occupied(ref node) if node.hash == hash && &node.key == key => {
+                            return Some(offset)
+                        }
                         // probe chain must continue
                         _ => (),
                     },
@@ -136,6 +444,38 @@ where
         }
     }
 
+    pub fn get(&self, key: K) -> Option<&V> {
+        let idx = self.probe(&key)?;
+        match &self.data[idx] {
+            Slot::Occupied(node) => Some(&node.value),
+            Slot::Empty => unreachable!(),
+        }
+    }
+
+    pub fn get_key_value(&self, key: K) -> Option<(&K, &V)> {
+        let idx = self.probe(&key)?;
+        match &self.data[idx] {
+            Slot::Occupied(node) => Some((&node.key, &node.value)),
+            Slot::Empty => unreachable!(),
+        }
+    }
+
+    pub fn contains_key(&self, key: K) -> bool {
+        self.probe(&key).is_some()
+    }
+
+    /// Returns a handle to `key`'s entry, whether or not it's currently occupied, hashing
+    /// `key` once. An occupied entry holds its slot index, so reading or updating it
+    /// takes no second probe; a vacant entry holds only the hash and key, and its
+    /// `insert` probes the table again for the slot to write.
+    pub fn entry(&mut self, key: K) -> Entry<'_, K, V, S> {
+        let hash = self.hash(&key);
+        match self.probe_hashed(hash, &key) {
+            Some(idx) => Entry::Occupied(OccupiedEntry { map: self, idx }),
+            None => Entry::Vacant(VacantEntry { map: self, hash, key }),
+        }
+    }
+
     #[inline(always)]
     fn exceeded_load_factor(&self) -> bool {
         self.capacity * 3 < (self.len + self.deleted) * 4
@@ -143,55 +483,260 @@ where
 
     #[inline(always)]
     pub fn set(&mut self, key: K, value: V) {
+        let hash = self.hash(&key);
+        self.insert_hashed(Node::new(hash, key, value));
+    }
+
+    /// Inserts an already-hashed node, growing the table first if needed. Returns the
+    /// index it was written to.
+    ///
+    /// If `node.key` already has an entry, this *updates* it in place rather than
+    /// inserting a second slot -- `set` on an existing key must not grow `len`.
+    /// Shared by `set` and `get_or_recompute` so both paths agree on probing/bookkeeping.
+    #[inline(always)]
+    fn insert_hashed(&mut self, node: Node<K, V>) -> usize {
+        #[cfg(feature = "diagnostics")]
+        self.check_canaries();
         if self.exceeded_load_factor() {
             self.resize();
         }
-        let hash = self.hash(&key);
-        let mut idx = self.modulo(hash);
+        let hash = node.hash;
+        #[cfg(feature = "diagnostics")]
+        self.record(JournalEntry::Insert(hash));
+        // `probe_hashed` skips tombstones, so an existing key is found even when a deleted
+        // slot lies before it on the probe chain; otherwise take the first free slot.
+        let mut idx = self.probe_hashed(hash, &node.key).unwrap_or(self.modulo(hash));
         loop {
-            match self.data[idx] {
-                Slot::Occupied(ref slot) if slot.hash != hash => idx = self.modulo(idx as u64 + 1),
-                _ => break,
+            match &self.data[idx] {
+                Slot::Occupied(slot) if slot.hash == hash && slot.key == node.key => break,
+                Slot::Occupied(_) => idx = self.modulo(idx as u64 + 1),
+                Slot::Empty => break,
             }
         }
+        let is_update = matches!(&self.data[idx], Slot::Occupied(slot) if slot.hash == hash && slot.key == node.key);
         let ctrl = ctrl_hash(hash);
         // bookkeeping so that memcpy can acquire contiguous values
         if (0..16).contains(&idx) {
             self.ctrl[self.capacity + idx] = ctrl;
         }
         self.ctrl[idx] = ctrl;
-        self.len += 1;
-        self.data[idx] = Slot::Occupied(Node { hash, value });
+        if !is_update {
+            self.len += 1;
+        }
+        self.data[idx] = Slot::Occupied(node);
+        idx
     }
 
-    pub fn delete(&mut self, key: K) {
+    /// Looks `key` up the same way `get` does, but guarantees a fresh value is returned:
+    ///
+    /// - absent/expired entries are recomputed unconditionally
+    /// - entries within their TTL are recomputed *early*, with probability that grows the
+    ///   closer `now` gets to `expiry`, per the XFetch algorithm (Vattani, Chierichetti &
+    ///   Lowenstein, "Optimal Probabilistic Cache Stampede Prevention"). This means
+    ///   concurrent callers racing the same hot key recompute at staggered times instead
+    ///   of all piling on at the instant it truly expires.
+    ///
+    /// `compute` is only invoked when a recompute is actually triggered, and is timed so
+    /// the measured cost (`delta`) feeds the early-expiration curve next time around.
+    ///
+    /// Takes `&mut self` rather than `&self`: a recompute can trigger `insert_hashed` ->
+    /// `resize`, which reallocates `data`/`ctrl`, and the returned `&V` borrows straight
+    /// out of `data`. Sharing `&StampedeMap` across threads and mutating it behind that
+    /// shared reference would let one caller's resize free memory a second caller is
+    /// still reading through an outstanding `&V` -- a use-after-free with no `unsafe` at
+    /// the call site. Requiring `&mut self` makes the borrow checker reject that instead:
+    /// callers that need real concurrent access should put the map behind a lock
+    /// (`Mutex`/`RwLock`) and use XFetch purely to stagger *when* they take it.
+    #[inline(never)]
+    pub fn get_or_recompute(&mut self, key: K, ttl: Duration, compute: impl FnOnce() -> V) -> &V {
+        // The probe below only *resolves* what to do -- it never acts on it. The
+        // `Slot::Occupied(ref node)` arm borrows `self.data` immutably, and that borrow is
+        // alive for the whole function because of the `-> &V` return type, so it can't
+        // coexist with the `&mut self` calls `recompute_at`/`recompute_new` need to make.
+        // Resolving to a plain `Resolution` first and only calling those once the loop
+        // (and its borrow of `self.data`/`self.ctrl`) has ended keeps the two phases from
+        // overlapping.
+        enum Resolution {
+            Fresh(usize),
+            Stale(usize),
+            New,
+        }
+
         let hash = self.hash(&key);
-        let mut idx = self.modulo(hash);
-        loop {
-            match &self.data[idx] {
-                Slot::Occupied(ref node) if node.hash == hash => break,
-                Slot::Empty => return,
-                _ => idx = self.modulo(idx as u64 + 1),
+        let ctrl = ctrl_hash(hash);
+        let now = Instant::now();
+        let mut slot = self.modulo(hash);
+        let resolution = loop {
+            // SAFETY: see `probe_hashed` -- `modulo` keeps every index in bounds.
+            let buffer = unsafe { self.ctrl.get_unchecked(slot..slot + 16) };
+            let empty_mask = BitMask::matches(buffer, Empty);
+            let ctrl_mask = BitMask::matches(buffer, ctrl);
+            let mut resolved = None;
+            for item in ctrl_mask | empty_mask {
+                let offset = self.modulo((slot + item as usize) as u64);
+                match unsafe { *self.ctrl.get_unchecked(offset) } {
+                    val if val == ctrl => match unsafe { self.data.get_unchecked(offset) } {
+                        Slot::Empty => unreachable!(),
+                        Slot::Occupied(ref node) if node.hash == hash && node.key == key => {
+                            resolved = Some(if Self::is_stale(node, now, self.beta) {
+                                Resolution::Stale(offset)
+                            } else {
+                                Resolution::Fresh(offset)
+                            });
+                            break;
+                        }
+                        _ => (),
+                    },
+                    Empty => {
+                        resolved = Some(Resolution::New);
+                        break;
+                    }
+                    _ => (),
+                }
             }
+            if let Some(resolution) = resolved {
+                break resolution;
+            }
+            slot = self.modulo(slot as u64 + 16);
+        };
+
+        match resolution {
+            Resolution::Fresh(offset) => match &self.data[offset] {
+                Slot::Occupied(node) => &node.value,
+                Slot::Empty => unreachable!(),
+            },
+            Resolution::Stale(offset) => self.recompute_at(offset, hash, key, ttl, compute),
+            Resolution::New => self.recompute_new(hash, key, ttl, compute),
+        }
+    }
+
+    /// The XFetch early-expiration test: stale early when
+    /// `now - delta * beta * ln(rand::<f64>()) >= expiry`, rearranged to avoid
+    /// subtracting durations that could underflow.
+    #[inline(always)]
+    fn is_stale(node: &Node<K, V>, now: Instant, beta: f64) -> bool {
+        if node.delta.is_zero() {
+            // absent/never-measured cost: treat like an unconditional miss
+            return true;
+        }
+        if now >= node.expiry {
+            return true;
+        }
+        // rand::random is [0, 1); clamp away from 0 so ln() can't produce -inf
+        let r: f64 = rand::random::<f64>().max(f64::MIN_POSITIVE);
+        let jitter = node.delta.mul_f64((beta * -r.ln()).max(0.0));
+        match now.checked_add(jitter) {
+            Some(early_deadline) => early_deadline >= node.expiry,
+            None => true,
+        }
+    }
+
+    /// Recomputes the value stored at `offset` and writes it back in place.
+    ///
+    /// Takes `&mut self` (see `get_or_recompute`): writing `self.data[offset]` in place
+    /// needs a real exclusive borrow, not a shared one cast away.
+    #[inline(always)]
+    fn recompute_at(&mut self, offset: usize, hash: u64, key: K, ttl: Duration, compute: impl FnOnce() -> V) -> &V {
+        #[cfg(feature = "diagnostics")]
+        self.check_canaries();
+        #[cfg(feature = "diagnostics")]
+        self.record(JournalEntry::Insert(hash));
+        let start = Instant::now();
+        let value = compute();
+        let delta = start.elapsed();
+        self.data[offset] = Slot::Occupied(Node {
+            hash,
+            key,
+            value,
+            expiry: start + ttl,
+            delta,
+        });
+        match &self.data[offset] {
+            Slot::Occupied(node) => &node.value,
+            Slot::Empty => unreachable!(),
         }
+    }
+
+    /// Computes and inserts a brand-new entry for a key that wasn't found at all.
+    ///
+    /// Takes `&mut self` (see `get_or_recompute`); this additionally touches
+    /// `len`/`ctrl`/`capacity` bookkeeping via `insert_hashed`, which may resize and so
+    /// genuinely needs the exclusive borrow, not just the write to `data[idx]`.
+    #[inline(always)]
+    fn recompute_new(&mut self, hash: u64, key: K, ttl: Duration, compute: impl FnOnce() -> V) -> &V {
+        let start = Instant::now();
+        let value = compute();
+        let delta = start.elapsed();
+        let idx = self.insert_hashed(Node {
+            hash,
+            key,
+            value,
+            expiry: start + ttl,
+            delta,
+        });
+        match &self.data[idx] {
+            Slot::Occupied(node) => &node.value,
+            Slot::Empty => unreachable!(),
+        }
+    }
+
+    pub fn delete(&mut self, key: K) -> Option<V> {
+        #[cfg(feature = "diagnostics")]
+        self.check_canaries();
+        let hash = self.hash(&key);
+        #[cfg(feature = "diagnostics")]
+        self.record(JournalEntry::Remove(hash));
+        let idx = self.probe_hashed(hash, &key)?;
         if (0..16).contains(&idx) {
             self.ctrl[self.capacity + idx] = Deleted;
         }
         self.ctrl[idx] = Deleted;
-        self.data[idx] = Slot::Empty;
+        let removed = mem::replace(&mut self.data[idx], Slot::Empty);
         self.deleted += 1;
         self.len -= 1;
+        match removed {
+            Slot::Occupied(node) => Some(node.value),
+            Slot::Empty => unreachable!(),
+        }
     }
 
     #[inline(always)]
     fn resize(&mut self) {
-        let mut old = Vec::with_capacity(self.capacity);
-        self.capacity = (self.capacity() + 1).next_power_of_two();
+        let target = (self.capacity() + 1).next_power_of_two();
+        self.try_grow_to(target)
+            .expect("StampedeMap: allocation failed while growing the table")
+    }
+
+    /// Fallible core of `resize`/`try_reserve`: allocates fresh `data`/`ctrl` buffers of
+    /// at least `new_capacity` and rehashes every occupied entry into them, only then
+    /// swapping them into `self`. If either allocation fails, `self` is left completely
+    /// untouched -- the map stays exactly as usable as it was before the call.
+    fn try_grow_to(&mut self, new_capacity: usize) -> Result<(), TryReserveError> {
+        #[cfg(feature = "diagnostics")]
+        self.check_canaries();
+        if new_capacity <= self.capacity {
+            return Ok(());
+        }
+        let new_data_len = data_alloc_len(new_capacity);
+        let mut new_data = Vec::new();
+        new_data
+            .try_reserve_exact(new_data_len)
+            .map_err(|_| TryReserveError::new(new_data_len))?;
+        new_data.resize(new_data_len, Slot::Empty);
+
+        let new_ctrl_len = ctrl_alloc_len(new_capacity);
+        let mut new_ctrl = Vec::new();
+        new_ctrl
+            .try_reserve_exact(new_ctrl_len)
+            .map_err(|_| TryReserveError::new(new_ctrl_len))?;
+        new_ctrl.resize(new_ctrl_len, Empty);
+
+        let old = mem::replace(&mut self.data, new_data);
+        self.ctrl = new_ctrl;
+        self.capacity = new_capacity;
         self.deleted = 0;
-        mem::swap(&mut old, &mut self.data);
-        self.ctrl.clear();
-        self.ctrl.resize(self.capacity + 16, Empty);
-        self.data.resize(self.capacity, Slot::Empty);
+        #[cfg(feature = "diagnostics")]
+        self.write_ctrl_canary();
         for slot in old {
             // we don't need to preserve deleted values and empty is a no-op
             if let Slot::Occupied(node) = &slot {
@@ -200,7 +745,8 @@ where
                 loop {
                     match self.data[idx] {
                         Slot::Empty => break,
-                        // duplicate hashes are impossible in a bijective map
+                        // every entry in `old` is already a distinct key, so the first
+                        // empty slot along the probe chain is always correct here
                         _ => idx = self.modulo(idx as u64 + 1),
                     }
                 }
@@ -212,6 +758,37 @@ where
                 self.data[idx] = slot;
             }
         }
+        Ok(())
+    }
+
+    /// Reserves capacity for at least `additional` more entries without aborting the
+    /// process if the allocator can't satisfy it -- the fallible counterpart to the
+    /// implicit growth `set` performs. Leaves the map unchanged on failure.
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        let needed = self.len + self.deleted + additional;
+        let mut target = self.capacity.max(bucket_size());
+        while target * 3 < needed * 4 {
+            target = (target + 1).next_power_of_two();
+        }
+        self.try_grow_to(target)
+    }
+
+    /// Fallible counterpart to [`Self::with_capacity`].
+    pub fn try_with_capacity(cap: usize) -> Result<Self, TryReserveError> {
+        let mut map = Self::new();
+        map.try_reserve(cap)?;
+        Ok(map)
+    }
+
+    /// Fallible counterpart to [`Self::set`]: grows the table first if needed, but
+    /// returns a [`TryReserveError`] instead of aborting when that growth can't
+    /// allocate, leaving the map unchanged.
+    pub fn try_insert(&mut self, key: K, value: V) -> Result<(), TryReserveError> {
+        if self.exceeded_load_factor() {
+            self.try_reserve(1)?;
+        }
+        self.set(key, value);
+        Ok(())
     }
 
     #[inline(always)]
@@ -230,6 +807,448 @@ where
     fn modulo(&self, offset: u64) -> usize {
         (offset & ((self.capacity - 1) as u64)) as usize
     }
+
+    /// Writes [`CANARY`] into the trailing guard bytes of the *actual* `ctrl` allocation
+    /// (see `ctrl_alloc_len`). Called every time `ctrl` is (re)allocated -- `new`,
+    /// `with_capacity`, `clear`, `drain`, `try_grow_to` -- so the guard always matches the
+    /// buffer currently backing `self.ctrl`.
+    #[cfg(feature = "diagnostics")]
+    #[inline(always)]
+    fn write_ctrl_canary(&mut self) {
+        let at = self.capacity + bucket_size();
+        self.ctrl[at..at + CANARY_GUARD_LEN].copy_from_slice(&CANARY.to_ne_bytes());
+    }
+
+    /// Checks the trailing guard bytes past the end of `ctrl`'s bookkeeping mirror group,
+    /// and the trailing guard slot past the end of `data`, panicking with the journal
+    /// dumped if either was overwritten. Called at the start of every operation that
+    /// inserts, removes, clears or reallocates, so a heap-overwrite bug that walked off
+    /// the end of either allocation is caught at the call that first observes it, not at
+    /// whatever later operation happens to read the corrupted memory. Changing a value in
+    /// place through `iter_mut`, `values_mut` or `OccupiedEntry::get_mut`/`into_mut` does
+    /// not check the canaries.
+    #[cfg(feature = "diagnostics")]
+    #[inline(always)]
+    fn check_canaries(&self) {
+        let at = self.capacity + bucket_size();
+        let ctrl_guard = &self.ctrl[at..at + CANARY_GUARD_LEN];
+        let ctrl_ok = ctrl_guard == CANARY.to_ne_bytes();
+        let data_ok = matches!(self.data[self.capacity], Slot::Empty);
+        if !ctrl_ok || !data_ok {
+            panic!(
+                "StampedeMap: canary corrupted (ctrl guard = {:x?}, expected {:x?}; data guard occupied = {}); recent operations: {:?}",
+                ctrl_guard,
+                CANARY.to_ne_bytes(),
+                !data_ok,
+                self.journal.borrow().ordered(),
+            );
+        }
+    }
+
+    /// Panics with the journal dumped, for invariant violations that aren't a canary
+    /// mismatch -- e.g. a control byte claiming a slot is occupied when `data` disagrees.
+    #[cfg(feature = "diagnostics")]
+    #[inline(always)]
+    fn bug(&self, what: &str) -> ! {
+        panic!(
+            "StampedeMap: invariant violated ({}); recent operations: {:?}",
+            what,
+            self.journal.borrow().ordered(),
+        );
+    }
+
+    /// Appends to the journal. Takes `&self` because `probe` (the read path, shared by
+    /// `get`/`get_key_value`/`contains_key`/`delete`/`entry`) only takes `&self` -- the
+    /// journal is a `RefCell` for exactly that reason, rather than casting away the
+    /// shared borrow. The journal is purely diagnostic bookkeeping with no effect on map
+    /// correctness either way.
+    #[cfg(feature = "diagnostics")]
+    #[inline(always)]
+    fn record(&self, entry: JournalEntry) {
+        self.journal.borrow_mut().record(entry);
+    }
+}
+
+/// Borrowing iterator over a `StampedeMap`'s occupied entries. See [`StampedeMap::iter`].
+pub struct Iter<'a, K, V>
+where
+    K: Clone,
+    V: Clone,
+{
+    ctrl: &'a [u8],
+    data: &'a [Slot<K, V>],
+    group: usize,
+    mask: BitMask,
+}
+
+impl<'a, K, V> Iter<'a, K, V>
+where
+    K: Clone,
+    V: Clone,
+{
+    fn new(ctrl: &'a [u8], data: &'a [Slot<K, V>]) -> Self {
+        let mask = BitMask::new(occupied_mask(&ctrl[0..16]));
+        Self {
+            ctrl,
+            data,
+            group: 0,
+            mask,
+        }
+    }
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V>
+where
+    K: Clone,
+    V: Clone,
+{
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(item) = self.mask.next() {
+                let idx = self.group * 16 + item as usize;
+                return match &self.data[idx] {
+                    Slot::Occupied(node) => Some((&node.key, &node.value)),
+                    Slot::Empty => unreachable!(),
+                };
+            }
+            self.group += 1;
+            let start = self.group * 16;
+            if start >= self.data.len() {
+                return None;
+            }
+            self.mask = BitMask::new(occupied_mask(&self.ctrl[start..start + 16]));
+        }
+    }
+}
+
+/// Borrowing iterator over a `StampedeMap`'s occupied entries with mutable values. See
+/// [`StampedeMap::iter_mut`].
+pub struct IterMut<'a, K, V>
+where
+    K: Clone,
+    V: Clone,
+{
+    ctrl: &'a [u8],
+    data: *mut Slot<K, V>,
+    len: usize,
+    group: usize,
+    mask: BitMask,
+    _marker: PhantomData<&'a mut Slot<K, V>>,
+}
+
+impl<'a, K, V> IterMut<'a, K, V>
+where
+    K: Clone,
+    V: Clone,
+{
+    fn new(ctrl: &'a [u8], data: &'a mut [Slot<K, V>]) -> Self {
+        let mask = BitMask::new(occupied_mask(&ctrl[0..16]));
+        Self {
+            ctrl,
+            len: data.len(),
+            data: data.as_mut_ptr(),
+            group: 0,
+            mask,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<'a, K, V> Iterator for IterMut<'a, K, V>
+where
+    K: Clone,
+    V: Clone,
+{
+    type Item = (&'a K, &'a mut V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(item) = self.mask.next() {
+                let idx = self.group * 16 + item as usize;
+                // SAFETY: every occupied slot is yielded exactly once across the
+                // lifetime of this iterator, so no two `&mut` borrows ever alias.
+                let slot = unsafe { &mut *self.data.add(idx) };
+                return match slot {
+                    Slot::Occupied(node) => Some((&node.key, &mut node.value)),
+                    Slot::Empty => unreachable!(),
+                };
+            }
+            self.group += 1;
+            let start = self.group * 16;
+            if start >= self.len {
+                return None;
+            }
+            self.mask = BitMask::new(occupied_mask(&self.ctrl[start..start + 16]));
+        }
+    }
+}
+
+/// Owning iterator that drains a `StampedeMap`'s entries. See [`StampedeMap::drain`].
+pub struct Drain<K, V>
+where
+    K: Clone,
+    V: Clone,
+{
+    inner: std::vec::IntoIter<Slot<K, V>>,
+}
+
+impl<K, V> Iterator for Drain<K, V>
+where
+    K: Clone,
+    V: Clone,
+{
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for slot in &mut self.inner {
+            if let Slot::Occupied(node) = slot {
+                return Some((node.key, node.value));
+            }
+        }
+        None
+    }
+}
+
+/// Owning iterator produced by `IntoIterator for StampedeMap<K, V, S>`.
+pub struct IntoIter<K, V>
+where
+    K: Clone,
+    V: Clone,
+{
+    inner: std::vec::IntoIter<Slot<K, V>>,
+}
+
+impl<K, V> Iterator for IntoIter<K, V>
+where
+    K: Clone,
+    V: Clone,
+{
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for slot in &mut self.inner {
+            if let Slot::Occupied(node) = slot {
+                return Some((node.key, node.value));
+            }
+        }
+        None
+    }
+}
+
+impl<K, V, S> IntoIterator for StampedeMap<K, V, S>
+where
+    K: Clone,
+    V: Clone,
+{
+    type Item = (K, V);
+    type IntoIter = IntoIter<K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter {
+            inner: self.data.into_iter(),
+        }
+    }
+}
+
+impl<'a, K, V, S> IntoIterator for &'a StampedeMap<K, V, S>
+where
+    K: Hash + Sized + CallHasher + Eq + Clone,
+    V: Clone + std::fmt::Debug,
+    S: BuildHasher + Default,
+{
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<K, V, S> FromIterator<(K, V)> for StampedeMap<K, V, S>
+where
+    K: Hash + Sized + CallHasher + Eq + Clone,
+    V: Clone + std::fmt::Debug,
+    S: BuildHasher + Default,
+{
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = Self::new();
+        map.extend(iter);
+        map
+    }
+}
+
+impl<K, V, S> Extend<(K, V)> for StampedeMap<K, V, S>
+where
+    K: Hash + Sized + CallHasher + Eq + Clone,
+    V: Clone + std::fmt::Debug,
+    S: BuildHasher + Default,
+{
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (k, v) in iter {
+            self.set(k, v);
+        }
+    }
+}
+
+/// A handle to a single slot in a `StampedeMap`, returned by [`StampedeMap::entry`].
+pub enum Entry<'a, K, V, S = ahash::RandomState>
+where
+    K: Clone,
+    V: Clone,
+{
+    Occupied(OccupiedEntry<'a, K, V, S>),
+    Vacant(VacantEntry<'a, K, V, S>),
+}
+
+impl<'a, K, V, S> Entry<'a, K, V, S>
+where
+    K: Hash + Sized + CallHasher + Eq + Clone,
+    V: Clone + std::fmt::Debug,
+    S: BuildHasher + Default,
+{
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default),
+        }
+    }
+
+    pub fn or_insert_with(self, default: impl FnOnce() -> V) -> &'a mut V {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default()),
+        }
+    }
+
+    pub fn or_default(self) -> &'a mut V
+    where
+        V: Default,
+    {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(V::default()),
+        }
+    }
+
+    pub fn and_modify(self, f: impl FnOnce(&mut V)) -> Self {
+        match self {
+            Entry::Occupied(mut entry) => {
+                f(entry.get_mut());
+                Entry::Occupied(entry)
+            }
+            Entry::Vacant(entry) => Entry::Vacant(entry),
+        }
+    }
+}
+
+/// See [`Entry::Occupied`].
+pub struct OccupiedEntry<'a, K, V, S = ahash::RandomState>
+where
+    K: Clone,
+    V: Clone,
+{
+    map: &'a mut StampedeMap<K, V, S>,
+    idx: usize,
+}
+
+impl<'a, K, V, S> OccupiedEntry<'a, K, V, S>
+where
+    K: Hash + Sized + CallHasher + Eq + Clone,
+    V: Clone + std::fmt::Debug,
+    S: BuildHasher + Default,
+{
+    pub fn get(&self) -> &V {
+        match &self.map.data[self.idx] {
+            Slot::Occupied(node) => &node.value,
+            Slot::Empty => unreachable!(),
+        }
+    }
+
+    pub fn get_mut(&mut self) -> &mut V {
+        match &mut self.map.data[self.idx] {
+            Slot::Occupied(node) => &mut node.value,
+            Slot::Empty => unreachable!(),
+        }
+    }
+
+    pub fn into_mut(self) -> &'a mut V {
+        match &mut self.map.data[self.idx] {
+            Slot::Occupied(node) => &mut node.value,
+            Slot::Empty => unreachable!(),
+        }
+    }
+
+    /// Replaces the stored value, returning the one that was there before.
+    pub fn insert(&mut self, value: V) -> V {
+        #[cfg(feature = "diagnostics")]
+        self.map.check_canaries();
+        #[cfg(feature = "diagnostics")]
+        {
+            let hash = match &self.map.data[self.idx] {
+                Slot::Occupied(node) => node.hash,
+                Slot::Empty => unreachable!(),
+            };
+            self.map.record(JournalEntry::Insert(hash));
+        }
+        match &mut self.map.data[self.idx] {
+            Slot::Occupied(node) => mem::replace(&mut node.value, value),
+            Slot::Empty => unreachable!(),
+        }
+    }
+
+    /// Removes this entry from the map, same bookkeeping as [`StampedeMap::delete`].
+    pub fn remove(self) -> V {
+        let OccupiedEntry { map, idx } = self;
+        #[cfg(feature = "diagnostics")]
+        map.check_canaries();
+        if (0..16).contains(&idx) {
+            map.ctrl[map.capacity + idx] = Deleted;
+        }
+        map.ctrl[idx] = Deleted;
+        let removed = mem::replace(&mut map.data[idx], Slot::Empty);
+        map.deleted += 1;
+        map.len -= 1;
+        match removed {
+            Slot::Occupied(node) => {
+                #[cfg(feature = "diagnostics")]
+                map.record(JournalEntry::Remove(node.hash));
+                node.value
+            }
+            Slot::Empty => unreachable!(),
+        }
+    }
+}
+
+/// See [`Entry::Vacant`].
+pub struct VacantEntry<'a, K, V, S = ahash::RandomState>
+where
+    K: Clone,
+    V: Clone,
+{
+    map: &'a mut StampedeMap<K, V, S>,
+    hash: u64,
+    key: K,
+}
+
+impl<'a, K, V, S> VacantEntry<'a, K, V, S>
+where
+    K: Hash + Sized + CallHasher + Eq + Clone,
+    V: Clone + std::fmt::Debug,
+    S: BuildHasher + Default,
+{
+    /// Probes for a free slot and writes `value` there, growing the table first via
+    /// `exceeded_load_factor` if needed, and returns a mutable reference to the stored
+    /// value.
+    pub fn insert(self, value: V) -> &'a mut V {
+        let VacantEntry { map, hash, key } = self;
+        let idx = map.insert_hashed(Node::new(hash, key, value));
+        match &mut map.data[idx] {
+            Slot::Occupied(node) => &mut node.value,
+            Slot::Empty => unreachable!(),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -281,6 +1300,156 @@ mod tests {
         }
     }
 
+    #[test]
+    fn set_updates_existing_key_in_place() {
+        let mut map: StampedeMap<usize, usize> = StampedeMap::new();
+        map.set(42, 1);
+        assert_eq!(map.len(), 1);
+        map.set(42, 2);
+        assert_eq!(map.len(), 1);
+        assert_eq!(map.get(42), Some(&2));
+    }
+
+    #[test]
+    fn distinct_keys_stay_distinct() {
+        let mut map: StampedeMap<usize, usize> = StampedeMap::new();
+        let a = 0;
+        let b = 1;
+        map.set(a, 10);
+        map.set(b, 20);
+        assert_eq!(map.get(a), Some(&10));
+        assert_eq!(map.get(b), Some(&20));
+        assert!(map.contains_key(a));
+        assert_eq!(map.get_key_value(b), Some((&b, &20)));
+    }
+
+    #[test]
+    fn delete_returns_the_removed_value() {
+        let mut map: StampedeMap<usize, usize> = StampedeMap::new();
+        map.set(7, 99);
+        assert_eq!(map.delete(7), Some(99));
+        assert_eq!(map.delete(7), None);
+        assert!(!map.contains_key(7));
+    }
+
+    #[test]
+    fn iter_visits_every_occupied_entry() {
+        let mut map: StampedeMap<usize, usize> = StampedeMap::new();
+        for i in 0..20 {
+            map.set(i, i * 2);
+        }
+        let mut seen: Vec<(usize, usize)> = map.iter().map(|(k, v)| (*k, *v)).collect();
+        seen.sort_unstable();
+        let expected: Vec<(usize, usize)> = (0..20).map(|i| (i, i * 2)).collect();
+        assert_eq!(seen, expected);
+    }
+
+    #[test]
+    fn iter_mut_can_update_values_in_place() {
+        let mut map: StampedeMap<usize, usize> = StampedeMap::new();
+        map.set(1, 1);
+        map.set(2, 2);
+        for (_, v) in map.iter_mut() {
+            *v *= 10;
+        }
+        assert_eq!(map.get(1), Some(&10));
+        assert_eq!(map.get(2), Some(&20));
+    }
+
+    #[test]
+    fn retain_drops_entries_that_fail_the_predicate() {
+        let mut map: StampedeMap<usize, usize> = StampedeMap::new();
+        for i in 0..10 {
+            map.set(i, i);
+        }
+        map.retain(|_, v| *v % 2 == 0);
+        assert_eq!(map.len(), 5);
+        for i in 0..10 {
+            assert_eq!(map.contains_key(i), i % 2 == 0);
+        }
+    }
+
+    #[test]
+    fn drain_empties_the_map_and_yields_every_pair() {
+        let mut map: StampedeMap<usize, usize> = StampedeMap::new();
+        for i in 0..5 {
+            map.set(i, i);
+        }
+        let mut drained: Vec<(usize, usize)> = map.drain().collect();
+        drained.sort_unstable();
+        assert_eq!(drained, (0..5).map(|i| (i, i)).collect::<Vec<_>>());
+        assert!(map.is_empty());
+        assert_eq!(map.get(0), None);
+    }
+
+    #[test]
+    fn from_iterator_and_extend_round_trip() {
+        let map: StampedeMap<usize, usize> = (0..5).map(|i| (i, i * i)).collect();
+        assert_eq!(map.len(), 5);
+        for i in 0..5 {
+            assert_eq!(map.get(i), Some(&(i * i)));
+        }
+    }
+
+    #[test]
+    fn entry_or_insert_counts_occurrences() {
+        let mut map: StampedeMap<&str, usize> = StampedeMap::new();
+        for word in ["a", "b", "a", "c", "a", "b"] {
+            *map.entry(word).or_insert(0) += 1;
+        }
+        assert_eq!(map.get("a"), Some(&3));
+        assert_eq!(map.get("b"), Some(&2));
+        assert_eq!(map.get("c"), Some(&1));
+    }
+
+    #[test]
+    fn entry_and_modify_only_runs_when_occupied() {
+        let mut map: StampedeMap<usize, usize> = StampedeMap::new();
+        map.entry(1).and_modify(|v| *v += 1).or_insert(10);
+        assert_eq!(map.get(1), Some(&10));
+        map.entry(1).and_modify(|v| *v += 1).or_insert(10);
+        assert_eq!(map.get(1), Some(&11));
+    }
+
+    #[test]
+    fn occupied_entry_remove_returns_the_value() {
+        let mut map: StampedeMap<usize, usize> = StampedeMap::new();
+        map.set(5, 50);
+        match map.entry(5) {
+            Entry::Occupied(entry) => assert_eq!(entry.remove(), 50),
+            Entry::Vacant(_) => panic!("expected an occupied entry"),
+        }
+        assert!(!map.contains_key(5));
+    }
+
+    #[test]
+    fn try_reserve_grows_capacity_and_keeps_entries() {
+        let mut map: StampedeMap<usize, usize> = StampedeMap::new();
+        map.set(1, 1);
+        let capacity_before = map.capacity();
+        assert!(map.try_reserve(1_000).is_ok());
+        assert!(map.capacity() > capacity_before);
+        assert_eq!(map.get(1), Some(&1));
+    }
+
+    #[test]
+    fn try_insert_grows_and_stores_the_value() {
+        let mut map: StampedeMap<usize, usize> = StampedeMap::new();
+        for i in 0..100 {
+            assert!(map.try_insert(i, i * 2).is_ok());
+        }
+        for i in 0..100 {
+            assert_eq!(map.get(i), Some(&(i * 2)));
+        }
+    }
+
+    #[test]
+    fn try_with_capacity_preallocates() {
+        let map: StampedeMap<usize, usize> = StampedeMap::try_with_capacity(500).unwrap();
+        assert!(map.capacity() >= 500);
+        assert!(map.is_empty());
+    }
+
     proptest! {
         #![proptest_config(ProptestConfig { result_cache: proptest::test_runner::basic_result_cache, cases: 16, ..Default::default() })]
         #[test]
@@ -296,4 +1465,64 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn get_or_recompute_fills_absent_entries() {
+        let mut map: StampedeMap<usize, usize> = StampedeMap::new();
+        let value = *map.get_or_recompute(1, Duration::from_secs(60), || 42);
+        assert_eq!(value, 42);
+        assert_eq!(map.get(1), Some(&42));
+    }
+
+    #[test]
+    fn get_or_recompute_skips_compute_within_ttl() {
+        let mut map: StampedeMap<usize, usize> = StampedeMap::new();
+        map.get_or_recompute(1, Duration::from_secs(60), || 1);
+        let value = *map.get_or_recompute(1, Duration::from_secs(60), || {
+            panic!("compute should not run again for a fresh entry")
+        });
+        assert_eq!(value, 1);
+    }
+
+    #[test]
+    fn get_or_recompute_recomputes_past_ttl() {
+        let mut map: StampedeMap<usize, usize> = StampedeMap::new();
+        map.get_or_recompute(1, Duration::ZERO, || 1);
+        // A `ttl` of zero sets `expiry` to the moment the first compute started, so by the
+        // next call `now >= expiry` and `is_stale` treats the entry as expired.
+        let value = *map.get_or_recompute(1, Duration::from_secs(60), || 2);
+        assert_eq!(value, 2);
+        assert_eq!(map.get(1), Some(&2));
+    }
+
+    #[cfg(feature = "diagnostics")]
+    #[test]
+    #[should_panic(expected = "StampedeMap: canary corrupted")]
+    fn corrupted_ctrl_guard_panics_with_journal() {
+        let mut map: StampedeMap<usize, usize> = StampedeMap::new();
+        map.set(1, 1);
+        // Simulate a `get_unchecked_mut` overrun past the end of `ctrl`'s bookkeeping
+        // mirror group -- the exact byte a real out-of-bounds probe write would land on.
+        let at = map.capacity() + bucket_size();
+        map.ctrl[at] ^= 0xFF;
+        map.set(2, 2);
+    }
+
+    #[cfg(feature = "diagnostics")]
+    #[test]
+    #[should_panic(expected = "StampedeMap: canary corrupted")]
+    fn corrupted_data_guard_panics_with_journal() {
+        let mut map: StampedeMap<usize, usize> = StampedeMap::new();
+        map.set(1, 1);
+        // Simulate a `get_unchecked_mut` overrun past the end of `data`'s logical table --
+        // the trailing guard slot should never be anything but `Slot::Empty`.
+        map.data[map.capacity()] = Slot::Occupied(Node {
+            hash: 0,
+            key: 0,
+            value: 0,
+            expiry: Instant::now(),
+            delta: Duration::ZERO,
+        });
+        map.set(2, 2);
+    }
 }